@@ -0,0 +1,158 @@
+// Copyright 2020 James Bostock. See the LICENSE file at the
+// top-level directory of this distribution.
+
+// An implementation of the head(1) command in Rust.
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Write;
+
+mod util;
+#[path = "../tail_ext.rs"]
+mod tail_ext;
+
+#[derive(Clone, Copy)]
+enum Count {
+    /// Number of lines. A negative value means "all but the last
+    /// |n| lines".
+    Lines(i64),
+    /// Number of bytes. A negative value means "all but the last
+    /// |n| bytes".
+    Bytes(i64)
+}
+
+/// Copies the first `n` lines of `input` to the standard output.
+fn head_first_lines(input: &mut util::Input, n: u64) -> io::Result<()> {
+    let mut reader = io::BufReader::new(input);
+    let mut out = io::stdout();
+    let mut buf = Vec::new();
+    let mut lines = 0;
+
+    while lines < n {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        out.write_all(&buf)?;
+        lines += 1;
+    }
+    Ok(())
+}
+
+/// Copies the first `n` bytes of `input` to the standard output.
+fn head_first_bytes(input: &mut util::Input, n: u64) -> io::Result<()> {
+    let mut out = io::stdout();
+    io::copy(&mut input.take(n), &mut out)?;
+    Ok(())
+}
+
+/// Prints the requested portion of `filename` to the standard
+/// output. Negative counts ("all but the last |n| lines/bytes") are
+/// delegated to `util::Input`, which already knows how to walk a
+/// seekable file backwards or buffer a non-seekable one.
+fn head(filename: &str, count: Count) -> io::Result<()> {
+    let mut input = util::Input::open(filename)?;
+    let mut out = io::stdout();
+
+    match count {
+        Count::Lines(n) if n >= 0 => head_first_lines(&mut input, n as u64),
+        Count::Bytes(n) if n >= 0 => head_first_bytes(&mut input, n as u64),
+        Count::Lines(n) => {
+            out.write_all(&input.read_all_but_last_lines(n.unsigned_abs())?)
+        },
+        Count::Bytes(n) => {
+            out.write_all(&input.read_all_but_last_bytes(n.unsigned_abs())?)
+        }
+    }
+}
+
+fn parse_count(s: &str, prog: &str) -> i64 {
+    match s.parse::<i64>() {
+        Ok(n) => n,
+        Err(_) => {
+            eprintln!("{}: invalid number of lines or bytes: {}", prog, s);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Rewrites the classic BSD `-NUM` shorthand (e.g. `-20`) into `-n
+/// NUM` before handing the arguments to `util::GetOpt`, which has no
+/// notion of a bare numeric option. Tokens already consumed as the
+/// value of `-n`/`-c`, and anything following a `--`, are passed
+/// through untouched.
+fn expand_shorthand(args: env::Args) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut expect_value = false;
+    let mut opts_done = false;
+
+    for arg in args {
+        if opts_done || expect_value {
+            expect_value = false;
+            out.push(arg);
+        } else if arg == "--" {
+            opts_done = true;
+            out.push(arg);
+        } else if arg == "-n" || arg == "-c" {
+            expect_value = true;
+            out.push(arg);
+        } else if arg.len() > 1 && arg.starts_with('-')
+            && arg[1..].chars().all(|c| c.is_ascii_digit()) {
+            out.push("-n".to_string());
+            out.push(arg[1..].to_string());
+        } else {
+            out.push(arg);
+        }
+    }
+    out
+}
+
+fn main() {
+    let mut args = env::args();
+    let prog = args.next().unwrap();
+    let mut count = Count::Lines(10);
+    let mut files: Vec<String> = Vec::new();
+    let getopt = util::GetOpt::new("n:c:", expand_shorthand(args).into_iter());
+
+    for optarg in getopt {
+        match optarg {
+            Ok(util::Arg::OptWithArg('n', val)) => {
+                count = Count::Lines(parse_count(&val, &prog));
+            },
+            Ok(util::Arg::OptWithArg('c', val)) => {
+                count = Count::Bytes(parse_count(&val, &prog));
+            },
+            Ok(util::Arg::Arg(arg)) => files.push(arg),
+            Ok(val) => {
+                eprintln!("{}: error: unexpected: {:?}", prog, val);
+                std::process::exit(1);
+            },
+            Err(e) => {
+                eprintln!("{}: error: {}", prog, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let multiple = files.len() > 1;
+    let mut first = true;
+    for filename in &files {
+        if multiple {
+            if !first {
+                println!();
+            }
+            println!("==> {} <==", filename);
+            first = false;
+        }
+
+        match head(filename, count) {
+            Ok(_) => {},
+            Err(e) => eprintln!("{}: {}: {}", prog, filename, e)
+        }
+    }
+}
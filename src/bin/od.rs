@@ -15,6 +15,8 @@ use std::io::Write;
 use std::num::ParseIntError;
 
 mod util;
+#[path = "../getopt_ext.rs"]
+mod getopt_ext;
 
 type FmtFn = fn(&mut BufWriter<Stdout>, &[u8]) -> io::Result<usize>;
 
@@ -179,37 +181,53 @@ fn main() {
     let mut args = env::args();
     let prog = args.next().unwrap();
     let mut offset : u64 = 0;
-    let mut offstr = String::from("0");
     let mut fmt_fns: Vec<FmtFn> = Vec::new();
-    let getopt = util::GetOpt::new("bcdho", args);
+    let getopt = util::GetOpt::with_long("bcdho", &[("help", false)], args)
+        .describe('b', "octal byte values")
+        .describe('c', "ASCII characters")
+        .describe('d', "decimal word values")
+        .describe('h', "hexadecimal word values")
+        .describe('o', "octal word values")
+        .describe_long("help", "print this help message");
+    let usage = getopt.usage(&prog, "[-bcdho] [+offset] [file]");
+
+    let matches = match getopt.parse() {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!("{}: error: {}", prog, e);
+            std::process::exit(1);
+        }
+    };
+
+    if matches.opt_present("help") {
+        print!("{}", usage);
+        std::process::exit(0);
+    }
 
     // Default to reading from standard input.
+    let mut offstr = String::from("0");
     let mut filename = String::from("-");
 
-    for arg in getopt {
-	match arg {
-	    Ok(util::Arg::Opt('b')) => fmt_fns.push(write_oct_bytes),
-	    Ok(util::Arg::Opt('c')) => fmt_fns.push(write_ascii_chars),
-	    Ok(util::Arg::Opt('d')) => fmt_fns.push(write_dec_words),
-	    Ok(util::Arg::Opt('h')) => fmt_fns.push(write_hex_words),
-	    Ok(util::Arg::Opt('o')) => fmt_fns.push(write_oct_words),
-	    Ok(util::Arg::Arg(val)) => {
-		if val.starts_with('+') {
-		    offstr = val;
-		} else {
-		    filename = val;
-		}
-	    },
-	    Ok(val) => {
-		// Should never happen.
-		eprintln!("{}: error: unexpected: {:?}", prog, val);
-		std::process::exit(1);
-	    },
-	    Err(e) => {
-		eprintln!("{}: error: {}", prog, e);
-		std::process::exit(1);
-	    }
-	}
+    // Walk the occurrences in command-line order (rather than checking
+    // presence of each flag in a fixed sequence) so that repeated
+    // format flags (e.g. "-bb") each contribute a row, and flags given
+    // in different orders (e.g. "-od" vs "-do") print in that order.
+    for arg in matches.occurrences() {
+        match arg {
+            util::Arg::Opt('b') => fmt_fns.push(write_oct_bytes),
+            util::Arg::Opt('c') => fmt_fns.push(write_ascii_chars),
+            util::Arg::Opt('d') => fmt_fns.push(write_dec_words),
+            util::Arg::Opt('h') => fmt_fns.push(write_hex_words),
+            util::Arg::Opt('o') => fmt_fns.push(write_oct_words),
+            util::Arg::Arg(val) => {
+                if val.starts_with('+') {
+                    offstr = val.clone();
+                } else {
+                    filename = val.clone();
+                }
+            },
+            _ => {}
+        }
     }
 
     // If no output formats have been specified, default to octal words.
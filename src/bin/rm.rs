@@ -10,6 +10,24 @@ use std::io::Write;
 
 mod util;
 
+/// Removal options. Later flags win over earlier ones, so `-f`
+/// clears any `-i`/`-I` that preceded it (and vice versa).
+struct Options {
+    force: bool,
+    recursive: bool,
+    interactive: bool,
+    prompt_once: bool,
+    verbose: bool,
+    empty_dir: bool
+}
+
+impl Options {
+    fn new() -> Options {
+        Options {force: false, recursive: false, interactive: false,
+                  prompt_once: false, verbose: false, empty_dir: false}
+    }
+}
+
 /// Prompts user for confirmation
 fn confirm(msg: &str) -> io::Result<bool> {
     print!("{}: ", msg);
@@ -29,9 +47,21 @@ fn confirm(msg: &str) -> io::Result<bool> {
 }
 
 /// Removes a file or directory
-fn rm(name: &str, force: bool, recursive: bool) -> io::Result<()> {
-    let md = fs::metadata(name)?;
-    let go = if !force && md.permissions().readonly() {
+fn rm(name: &str, opts: &Options) -> io::Result<()> {
+    let md = match fs::metadata(name) {
+        Ok(md) => md,
+        Err(e) => {
+            // -f silences missing-file errors.
+            return if opts.force { Ok(()) } else { Err(e) };
+        }
+    };
+
+    let go = if opts.interactive {
+        let mut msg = "rm: remove ".to_string();
+        msg.push_str(&name);
+        msg.push_str("?");
+        confirm(&msg)?
+    } else if !opts.force && md.permissions().readonly() {
         let mut msg = "rm: remove readonly file ".to_string();
         msg.push_str(&name);
         msg.push_str("?");
@@ -40,38 +70,60 @@ fn rm(name: &str, force: bool, recursive: bool) -> io::Result<()> {
         true
     };
 
-    if go {
-        if recursive {
+    if !go {
+        return Ok(());
+    }
+
+    let result = if md.is_dir() {
+        if opts.recursive {
             fs::remove_dir_all(name)
+        } else if opts.empty_dir {
+            fs::remove_dir(name)
         } else {
-            fs::remove_file(name)
+            let mut msg = name.to_string();
+            msg.push_str(": is a directory");
+            Err(io::Error::new(io::ErrorKind::Other, msg))
         }
     } else {
-        Ok(())
+        fs::remove_file(name)
+    };
+
+    if result.is_ok() && opts.verbose {
+        println!("removed '{}'", name);
     }
+
+    result
 }
 
 fn main() {
     let mut args = env::args();
     let prog = args.next().unwrap();
-    let mut force: bool = false;
-    let mut recursive: bool = false;
+    let mut opts = Options::new();
     let mut print_usage = true;
-    let getopt = util::GetOpt::new("rf", args);
+    let mut files: Vec<String> = Vec::new();
+    let getopt = util::GetOpt::new("rfiIvd", args);
 
     for optarg in getopt {
         match optarg {
-            Ok(util::Arg::Opt('f')) => force = true,
-            Ok(util::Arg::Opt('r')) => recursive = true,
-            Ok(util::Arg::Arg(arg)) => {
-                match rm(&arg, force, recursive) {
-                    Ok(_) => print_usage = false,
-                    Err(e) => {
-                        eprintln!("{}: {}", arg, e);
-                        std::process::exit(1);
-                    }
-                }
-            }
+            Ok(util::Arg::Opt('f')) => {
+                opts.force = true;
+                opts.interactive = false;
+                opts.prompt_once = false;
+            },
+            Ok(util::Arg::Opt('r')) => opts.recursive = true,
+            Ok(util::Arg::Opt('i')) => {
+                opts.interactive = true;
+                opts.force = false;
+                opts.prompt_once = false;
+            },
+            Ok(util::Arg::Opt('I')) => {
+                opts.prompt_once = true;
+                opts.force = false;
+                opts.interactive = false;
+            },
+            Ok(util::Arg::Opt('v')) => opts.verbose = true,
+            Ok(util::Arg::Opt('d')) => opts.empty_dir = true,
+            Ok(util::Arg::Arg(arg)) => files.push(arg),
             Ok(val) => {
                 eprintln!("{}: error: unexpected: {:?}", prog, val);
                 std::process::exit(1);
@@ -83,8 +135,36 @@ fn main() {
         }
     }
 
+    // -I prompts once, before the removal loop starts, if we are
+    // about to remove more than three files or recurse into a
+    // directory.
+    if opts.prompt_once && !opts.force
+        && (files.len() > 3 || opts.recursive) {
+        let mut msg = "rm: remove ".to_string();
+        msg.push_str(&files.len().to_string());
+        msg.push_str(" arguments?");
+        match confirm(&msg) {
+            Ok(true) => {},
+            Ok(false) => std::process::exit(0),
+            Err(e) => {
+                eprintln!("{}: error: {}", prog, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    for arg in &files {
+        match rm(arg, &opts) {
+            Ok(_) => print_usage = false,
+            Err(e) => {
+                eprintln!("{}: {}", arg, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     if print_usage {
-        eprintln!("usage: {} [-f][-r] file ...", prog);
+        eprintln!("usage: {} [-f][-i][-I][-r][-v][-d] file ...", prog);
         std::process::exit(1);
     }
     std::process::exit(0);
@@ -4,6 +4,14 @@
 use std::fs;
 use std::io::{self, Read, Seek, SeekFrom};
 
+// The binaries have no Cargo workspace to depend on the library
+// crate through, so we share the single getopt(3)-style parser by
+// pointing a module directly at its source file rather than
+// maintaining a second, independent implementation here.
+#[path = "../../getopt.rs"]
+pub(crate) mod getopt;
+pub use getopt::{Arg, GetOpt};
+
 // Utility routines
 
 // An input source.
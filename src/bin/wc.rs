@@ -17,13 +17,16 @@ mod util;
 struct Format {
     chars: bool, // Technically bytes.
     words: bool,
-    lines: bool
+    lines: bool,
+    mchars: bool, // True (multibyte-aware) character count.
+    max_line: bool
 }
 
 impl Format {
     fn new() -> Format {
         // By default, all values are printed.
-        Format {chars: false, words: false, lines: false}
+        Format {chars: false, words: false, lines: false, mchars: false,
+                max_line: false}
     }
 }
 
@@ -31,6 +34,9 @@ struct Counts<'a, 'b> {
     chars: usize,
     words: usize,
     lines: usize,
+    mchars: usize,
+    max_line: usize,
+    cur_col: usize,
     file: &'a str,
     in_word: bool,
     format: &'b Format
@@ -38,8 +44,8 @@ struct Counts<'a, 'b> {
 
 impl<'a, 'b> Counts<'a, 'b> {
     fn new(file: &'a str, format: &'b Format) -> Counts<'a, 'b> {
-        Counts {chars: 0, words: 0, lines: 0, file, in_word: false,
-                format}
+        Counts {chars: 0, words: 0, lines: 0, mchars: 0, max_line: 0,
+                cur_col: 0, file, in_word: false, format}
     }
 }
 
@@ -50,6 +56,9 @@ impl<'a, 'b> ops::AddAssign for Counts<'a, 'b> {
             chars: self.chars + rhs.chars,
             words: self.words + rhs.words,
             lines: self.lines + rhs.lines,
+            mchars: self.mchars + rhs.mchars,
+            max_line: self.max_line.max(rhs.max_line),
+            cur_col: self.cur_col,
             file: self.file,
             in_word: self.in_word,
             format: self.format
@@ -68,6 +77,12 @@ impl<'a, 'b> fmt::Display for Counts<'a, 'b> {
         if self.format.chars {
             let _ = write!(f, "{:7} ", self.chars);
         }
+        if self.format.mchars {
+            let _ = write!(f, "{:7} ", self.mchars);
+        }
+        if self.format.max_line {
+            let _ = write!(f, "{:7} ", self.max_line);
+        }
         write!(f, "{}", self.file)
     }
 }
@@ -93,6 +108,27 @@ impl<'a, 'b> io::Write for Counts<'a, 'b> {
                 self.in_word = true;
                 self.words += 1;
             }
+
+            // A byte that is not a UTF-8 continuation byte starts a
+            // new character, so a multibyte sequence split across
+            // write() calls is still only counted once.
+            let starts_char = *c & 0xC0 != 0x80;
+
+            if self.format.mchars && starts_char {
+                self.mchars += 1;
+            }
+
+            if self.format.max_line {
+                if *c == LF {
+                    self.cur_col = 0;
+                } else if *c == HT {
+                    self.cur_col = (self.cur_col / 8 + 1) * 8;
+                    self.max_line = self.max_line.max(self.cur_col);
+                } else if starts_char {
+                    self.cur_col += 1;
+                    self.max_line = self.max_line.max(self.cur_col);
+                }
+            }
         }
         self.chars += buf.len();
         Ok(buf.len())
@@ -115,7 +151,7 @@ fn main () {
     let prog = args.next().unwrap();
 
     let mut format = Format::new();
-    let getopt = util::GetOpt::new("clw", args);
+    let getopt = util::GetOpt::new("clmLw", args);
 
     let mut format_specified = false;
     let mut files : Vec<String> = Vec::new();
@@ -130,6 +166,14 @@ fn main () {
                 format.lines = true;
                 format_specified = true;
             },
+            Ok(util::Arg::Opt('m')) => {
+                format.mchars = true;
+                format_specified = true;
+            },
+            Ok(util::Arg::Opt('L')) => {
+                format.max_line = true;
+                format_specified = true;
+            },
             Ok(util::Arg::Opt('w')) => {
                 format.words = true;
                 format_specified = true;
@@ -2,6 +2,15 @@
 // directory of this distribution.
 
 //! A command line parser similar to getopt(3).
+//!
+//! The GNU-style long-option constructor (`with_long`), required-option
+//! builder (`reqopt`), usage/help rendering (`describe`/`describe_long`/
+//! `usage`) and the collected-results `Matches` type (`parse`) are
+//! `impl`ed onto [`GetOpt`] from a separate file, `getopt_ext.rs`,
+//! rather than from here. Every binary that parses its command line
+//! includes this file; only `od` currently needs that extra surface,
+//! and including it everywhere left the rest of the binaries full of
+//! `dead_code` warnings for associated items they never call.
 
 use std::fmt;
 use std::iter::Iterator;
@@ -14,23 +23,32 @@ pub enum Arg {
     Opt(char),
     /// A command line option with an argument.
     OptWithArg(char, String),
+    /// A long (`--name`) command line option without an argument.
+    LongOpt(String),
+    /// A long (`--name` or `--name=value`) command line option with
+    /// an argument.
+    LongOptWithArg(String, String),
     /// A command line argument.
     Arg(String)
 }
 
 /// A command line option specification.
-#[derive(Clone,Copy,Debug)]
-struct OptSpec {
+#[derive(Clone,Debug)]
+pub(crate) struct OptSpec {
     /// The option character.
-    opt: char,
+    pub(crate) opt: char,
     /// Indicates whether the option expects an argument.
-    has_arg: bool
+    pub(crate) has_arg: bool,
+    /// Indicates whether the option must appear at least once.
+    pub(crate) required: bool,
+    /// An optional human-readable description, used by `getopt_ext::usage`.
+    pub(crate) desc: Option<String>
 }
 
 impl OptSpec {
     /// Creates a new `OptSpec`.
-    fn new(opt: char, has_arg: bool) -> Self {
-	OptSpec {opt, has_arg}
+    fn new(opt: char, has_arg: bool, required: bool) -> Self {
+	OptSpec {opt, has_arg, required, desc: None}
     }
 }
 
@@ -40,43 +58,57 @@ where
     I: Iterator<Item = String>
 {
     /// Indicates whether all options have been parsed.
-    opts_done: bool,
+    pub(crate) opts_done: bool,
     /// The option specifications to use when parsing the command
     /// line.
-    opt_specs: Vec<OptSpec>,
+    pub(crate) opt_specs: Vec<OptSpec>,
+    /// The long option names, whether each expects an argument and an
+    /// optional description.
+    pub(crate) long_specs: Vec<(String, bool, Option<String>)>,
     /// The command line arguments.
-    args: I,
+    pub(crate) args: I,
     /// The letters of the current option argument (including the
     /// leading '-').
-    chars: Vec<char>,
+    pub(crate) chars: Vec<char>,
     /// The index of the current option in `chars`.
-    idx: usize
+    pub(crate) idx: usize,
+    /// The short options seen at least once so far.
+    pub(crate) seen: Vec<char>,
+    /// The required options not yet seen, computed once the command
+    /// line is exhausted and reported one per call to `next`.
+    pub(crate) missing_required: Option<Vec<char>>
 }
 
-/// Converts a getopt optstring to a vector of `OptSpec`s.
-fn parse_optstring(optstring: &str) -> Vec<OptSpec> {
+/// Converts a getopt optstring to a vector of `OptSpec`s. A `:`
+/// immediately after an option letter marks it as taking an argument;
+/// a `!` marks it as required (`reqopt`-style), in either order (e.g.
+/// `"a:!"` or `"a!:"`).
+pub(crate) fn parse_optstring(optstring: &str) -> Vec<OptSpec> {
     let mut opt_specs : Vec<OptSpec> = Vec::new();
-    let mut last : Option<char> = None;
+    let mut current : Option<(char, bool, bool)> = None;
     for char in optstring.chars() {
 	if char == ':' {
-	    match last {
-		Some(c) => opt_specs.push(OptSpec::new(c, true)),
-		None => {
-		    panic!("{}: invalid option specification", optstring);
-		}
+	    match &mut current {
+		Some((_, has_arg, _)) => *has_arg = true,
+		None => panic!("{}: invalid option specification", optstring)
+	    }
+	} else if char == '!' {
+	    match &mut current {
+		Some((_, _, required)) => *required = true,
+		None => panic!("{}: invalid option specification", optstring)
 	    }
 	} else if char.is_ascii_alphanumeric() {
-	    if let Some(c) = last {
-		opt_specs.push(OptSpec::new(c, false))
+	    if let Some((c, has_arg, required)) = current {
+		opt_specs.push(OptSpec::new(c, has_arg, required))
 	    }
-	    last = Some(char);
+	    current = Some((char, false, false));
 	} else {
 	    panic!("{}: invalid option specification", optstring);
 	}
     }
 
-    if let Some(c) = last {
-	opt_specs.push(OptSpec::new(c, false))
+    if let Some((c, has_arg, required)) = current {
+	opt_specs.push(OptSpec::new(c, has_arg, required))
     }
 
     opt_specs
@@ -104,10 +136,13 @@ where
 	let opt_specs = parse_optstring(optstring);
 	GetOpt {
 	    opt_specs,
+	    long_specs: Vec::new(),
 	    opts_done: false,
 	    args,
 	    chars: Vec::new(),
-	    idx: 0
+	    idx: 0,
+	    seen: Vec::new(),
+	    missing_required: None
 	}
     }
 
@@ -115,7 +150,18 @@ where
     fn find_opt_spec(&self, opt: char) -> Option<OptSpec> {
 	for opt_spec in &self.opt_specs {
 	    if opt_spec.opt == opt {
-		return Some(*opt_spec)
+		return Some(opt_spec.clone())
+	    }
+	}
+
+	None
+    }
+
+    /// Find the long option specification matching `name`.
+    fn find_long_spec(&self, name: &str) -> Option<bool> {
+	for (long_name, has_arg, _) in &self.long_specs {
+	    if long_name == name {
+		return Some(*has_arg)
 	    }
 	}
 
@@ -130,12 +176,17 @@ where
 	    self.chars = arg.chars().collect();
 	    self.idx = 1;
 	    if self.chars.len() > 1 {
-		if self.chars.len() == 2 && self.chars[1] == '-' {
-		    self.opts_done = true;
-		    self.idx = 0;
-		    match self.args.next() {
-			Some(arg) => self.handle_arg(&arg),
-			None => None
+		if self.chars[1] == '-' {
+		    if self.chars.len() == 2 {
+			self.opts_done = true;
+			self.idx = 0;
+			match self.args.next() {
+			    Some(arg) => self.handle_arg(&arg),
+			    None => None
+			}
+		    } else {
+			self.idx = self.chars.len();
+			Some(self.handle_long_option(arg))
 		    }
 		} else {
 		    Some(self.handle_option())
@@ -155,10 +206,17 @@ where
 	self.idx += 1;
 	match self.find_opt_spec(opt) {
 	    Some(opt_spec) => {
+		self.seen.push(opt);
 		if opt_spec.has_arg {
-		    match self.args.next() {
-			Some(arg) => Ok(Arg::OptWithArg(opt, arg)),
-			None => Err(GetOptErr::MissingArg(opt))
+		    if self.idx < self.chars.len() {
+			let rest: String = self.chars[self.idx..].iter().collect();
+			self.idx = self.chars.len();
+			Ok(Arg::OptWithArg(opt, rest))
+		    } else {
+			match self.args.next() {
+			    Some(arg) => Ok(Arg::OptWithArg(opt, arg)),
+			    None => Err(GetOptErr::MissingArg(opt))
+			}
 		    }
 		} else {
 		    Ok(Arg::Opt(opt))
@@ -167,6 +225,49 @@ where
 	    None => Err(GetOptErr::UnknownOpt(opt))
 	}
     }
+
+    /// Handle a long (`--name` or `--name=value`) command line option.
+    fn handle_long_option(&mut self, arg: &str) -> Result<Arg, GetOptErr> {
+	let rest = &arg[2..];
+	let (name, inline_val) = match rest.find('=') {
+	    Some(pos) => (&rest[..pos], Some(rest[pos + 1..].to_string())),
+	    None => (rest, None)
+	};
+	match self.find_long_spec(name) {
+	    Some(has_arg) => {
+		if has_arg {
+		    match inline_val {
+			Some(val) => Ok(Arg::LongOptWithArg(name.to_string(), val)),
+			None => match self.args.next() {
+			    Some(val) => Ok(Arg::LongOptWithArg(name.to_string(), val)),
+			    None => Err(GetOptErr::MissingLongArg(name.to_string()))
+			}
+		    }
+		} else {
+		    Ok(Arg::LongOpt(name.to_string()))
+		}
+	    },
+	    None => Err(GetOptErr::UnknownLongOpt(name.to_string()))
+	}
+    }
+
+    /// Reports any required options not yet seen, one per call, once
+    /// the command line has been exhausted.
+    fn check_missing_required(&mut self) -> Option<Result<Arg, GetOptErr>> {
+	if self.missing_required.is_none() {
+	    let missing: Vec<char> = self.opt_specs.iter()
+		.filter(|spec| spec.required && !self.seen.contains(&spec.opt))
+		.map(|spec| spec.opt)
+		.collect();
+	    self.missing_required = Some(missing);
+	}
+
+	match &mut self.missing_required {
+	    Some(missing) if !missing.is_empty() =>
+		Some(Err(GetOptErr::MissingRequiredOpt(missing.remove(0)))),
+	    _ => None
+	}
+    }
 }
 
 /// The error type for the getopt module.
@@ -175,19 +276,30 @@ pub enum GetOptErr {
     /// No argument found for a command line option that expects an
     /// argument.
     MissingArg(char),
+    /// No argument found for a long command line option that expects
+    /// an argument.
+    MissingLongArg(String),
     /// No option letter found after hyphen.
     MissingOpt,
+    /// A `reqopt`-marked required option was never seen.
+    MissingRequiredOpt(char),
     /// An unrecognised command line option (i.e. one not present in
     /// the option specification string).
-    UnknownOpt(char)
+    UnknownOpt(char),
+    /// An unrecognised long command line option (i.e. one not present
+    /// in the long option registry).
+    UnknownLongOpt(String)
 }
 
 impl fmt::Display for GetOptErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 	match self {
 	    GetOptErr::MissingArg(c) => write!(f, "-{}: expected an argument", c),
+	    GetOptErr::MissingLongArg(name) => write!(f, "--{}: expected an argument", name),
 	    GetOptErr::MissingOpt => write!(f, "Missing option letter"),
-	    GetOptErr::UnknownOpt(c) => write!(f, "-{}: unknown option", c)
+	    GetOptErr::MissingRequiredOpt(c) => write!(f, "-{}: required option not given", c),
+	    GetOptErr::UnknownOpt(c) => write!(f, "-{}: unknown option", c),
+	    GetOptErr::UnknownLongOpt(name) => write!(f, "--{}: unknown option", name)
 	}
     }
 }
@@ -208,7 +320,7 @@ where
 		Some(arg) => {
 		    self.handle_arg(&arg)
 		},
-		None => None
+		None => self.check_missing_required()
 	    }
 	}
     }
@@ -430,7 +542,7 @@ mod tests {
 
     #[test]
     fn test_getopt_grouped_opts_with_args() {
-	let args = ["-ab", "ant", "bat"].iter().map(|s| s.to_string());
+	let args = ["-a", "ant", "-b", "bat"].iter().map(|s| s.to_string());
 	let mut getopt = GetOpt::new("a:b:", args);
 	getopt_assert_opt_with_arg!(getopt.next(), 'a', "ant");
 	getopt_assert_opt_with_arg!(getopt.next(), 'b', "bat");
@@ -440,28 +552,65 @@ mod tests {
     #[test]
     fn test_getopt_grouped_opts_missing_arg() {
 	let args = ["-ab"].iter().map(|s| s.to_string());
-	let mut getopt = GetOpt::new("a:b", args);
+	let mut getopt = GetOpt::new("ab:", args);
+	getopt_assert_opt!(getopt.next(), 'a');
 	match getopt.next() {
 	    Some(arg) => {
 		match arg {
-		    Err(GetOptErr::MissingArg(opt)) => assert_eq!('a', opt),
+		    Err(GetOptErr::MissingArg(opt)) => assert_eq!('b', opt),
 		    Err(_) => panic!("Expected MissingArg error"),
 		    Ok(_) => panic!("Expected MissingArg error")
 		}
 	    },
 	    None => panic!()
 	};
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_opt_with_attached_arg() {
+	let args = ["-aant"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::new("a:", args);
+	getopt_assert_opt_with_arg!(getopt.next(), 'a', "ant");
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_grouped_opts_with_attached_arg() {
+	let args = ["-abofile"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::new("abo:", args);
+	getopt_assert_opt!(getopt.next(), 'a');
 	getopt_assert_opt!(getopt.next(), 'b');
+	getopt_assert_opt_with_arg!(getopt.next(), 'o', "file");
 	getopt_assert_no_more_args!(getopt.next());
     }
 
     #[test]
     fn test_getopt_grouped_opts_with_arg_and_arg() {
-	let args = ["-ab", "ant", "bat"].iter().map(|s| s.to_string());
+	let args = ["-ba", "ant", "bat"].iter().map(|s| s.to_string());
 	let mut getopt = GetOpt::new("a:b", args);
-	getopt_assert_opt_with_arg!(getopt.next(), 'a', "ant");
 	getopt_assert_opt!(getopt.next(), 'b');
+	getopt_assert_opt_with_arg!(getopt.next(), 'a', "ant");
 	getopt_assert_arg!(getopt.next(), "bat");
 	getopt_assert_no_more_args!(getopt.next());
     }
+
+    #[test]
+    fn test_getopt_reqopt_via_optstring_satisfied() {
+	let args = ["-f", "out"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::new("f:!", args);
+	getopt_assert_opt_with_arg!(getopt.next(), 'f', "out");
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_reqopt_via_optstring_missing() {
+	let args = Vec::<String>::new().into_iter();
+	let mut getopt = GetOpt::new("f:!", args);
+	match getopt.next() {
+	    Some(Err(GetOptErr::MissingRequiredOpt(c))) => assert_eq!('f', c),
+	    _ => panic!("Expected MissingRequiredOpt error")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
 }
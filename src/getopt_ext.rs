@@ -0,0 +1,322 @@
+// Copyright 2020, 2021 James Bostock. See the LICENSE file at the top-level
+// directory of this distribution.
+
+//! Extended `GetOpt` functionality split out of `getopt.rs`: the
+//! GNU-style long-option constructor (`with_long`), required-option
+//! builder (`reqopt`), usage/help rendering (`describe`/`describe_long`/
+//! `usage`) and the collected-results `Matches` type (`parse`). Kept in
+//! its own file, included only by the binaries that use it (currently
+//! just `od`), so the rest don't compile in associated items they never
+//! call.
+
+use std::collections::HashMap;
+
+use crate::util::getopt::{parse_optstring, GetOptErr};
+use crate::util::{Arg, GetOpt};
+
+impl <I> GetOpt<I>
+where
+    I: Iterator<Item = String>
+{
+    /// Creates a new `GetOpt` that also recognises the given GNU-style
+    /// long options (each a name and whether it expects an argument).
+    pub fn with_long(optstring: &str, long_opts: &[(&str, bool)], args: I) -> Self {
+	let opt_specs = parse_optstring(optstring);
+	let long_specs = long_opts.iter()
+	    .map(|(name, has_arg)| (name.to_string(), *has_arg, None))
+	    .collect();
+	GetOpt {
+	    opt_specs,
+	    long_specs,
+	    opts_done: false,
+	    args,
+	    chars: Vec::new(),
+	    idx: 0,
+	    seen: Vec::new(),
+	    missing_required: None
+	}
+    }
+
+    /// Marks `opt` as required: if it is never seen on the command
+    /// line, iteration ends with a `MissingRequiredOpt` error.
+    pub fn reqopt(mut self, opt: char) -> Self {
+	if let Some(spec) = self.opt_specs.iter_mut().find(|s| s.opt == opt) {
+	    spec.required = true;
+	}
+	self
+    }
+
+    /// Attaches a human-readable description to `opt`, used by `usage`.
+    pub fn describe(mut self, opt: char, desc: &str) -> Self {
+	if let Some(spec) = self.opt_specs.iter_mut().find(|s| s.opt == opt) {
+	    spec.desc = Some(desc.to_string());
+	}
+	self
+    }
+
+    /// Attaches a human-readable description to the long option `name`,
+    /// used by `usage`.
+    pub fn describe_long(mut self, name: &str, desc: &str) -> Self {
+	if let Some(spec) = self.long_specs.iter_mut().find(|(n, _, _)| n == name) {
+	    spec.2 = Some(desc.to_string());
+	}
+	self
+    }
+
+    /// Renders a usage message listing every registered option and its
+    /// description, if any.
+    pub fn usage(&self, prog: &str, brief: &str) -> String {
+	let mut out = format!("usage: {} {}\n", prog, brief);
+	for spec in &self.opt_specs {
+	    let flag = if spec.has_arg {
+		format!("-{} <arg>", spec.opt)
+	    } else {
+		format!("-{}", spec.opt)
+	    };
+	    match &spec.desc {
+		Some(desc) => out.push_str(&format!("    {:<14}{}\n", flag, desc)),
+		None => out.push_str(&format!("    {}\n", flag))
+	    }
+	}
+	for (name, has_arg, desc) in &self.long_specs {
+	    let flag = if *has_arg {
+		format!("--{} <arg>", name)
+	    } else {
+		format!("--{}", name)
+	    };
+	    match desc {
+		Some(desc) => out.push_str(&format!("    {:<14}{}\n", flag, desc)),
+		None => out.push_str(&format!("    {}\n", flag))
+	    }
+	}
+	out
+    }
+
+    /// Drains the iterator into a `Matches`, collecting every option
+    /// occurrence and positional argument up front, in the order they
+    /// appeared on the command line (see [`Matches::occurrences`]).
+    pub fn parse(self) -> Result<Matches, GetOptErr> {
+	let mut occurrences: Vec<Arg> = Vec::new();
+	for item in self {
+	    occurrences.push(item?);
+	}
+
+	let mut opts: HashMap<String, Vec<Option<String>>> = HashMap::new();
+	let mut free: Vec<String> = Vec::new();
+	for arg in &occurrences {
+	    match arg {
+		Arg::Opt(c) => opts.entry(c.to_string()).or_default().push(None),
+		Arg::OptWithArg(c, val) =>
+		    opts.entry(c.to_string()).or_default().push(Some(val.clone())),
+		Arg::LongOpt(name) => opts.entry(name.clone()).or_default().push(None),
+		Arg::LongOptWithArg(name, val) =>
+		    opts.entry(name.clone()).or_default().push(Some(val.clone())),
+		Arg::Arg(arg) => free.push(arg.clone())
+	    }
+	}
+
+	Ok(Matches {opts, free, occurrences})
+    }
+}
+
+/// The collected result of parsing a command line with `GetOpt::parse`.
+pub struct Matches {
+    opts: HashMap<String, Vec<Option<String>>>,
+    free: Vec<String>,
+    occurrences: Vec<Arg>
+}
+
+impl Matches {
+    /// Returns whether the option or long option `name` was seen at
+    /// least once.
+    pub fn opt_present(&self, name: &str) -> bool {
+	self.opts.contains_key(name)
+    }
+
+    /// Returns how many times the option or long option `name` was
+    /// seen.
+    pub fn opt_count(&self, name: &str) -> usize {
+	self.opts.get(name).map_or(0, |occurrences| occurrences.len())
+    }
+
+    /// Returns the argument of the last occurrence of `name`, if it has
+    /// one.
+    pub fn opt_str(&self, name: &str) -> Option<&str> {
+	self.opts.get(name)?.iter().rev().find_map(|val| val.as_deref())
+    }
+
+    /// Returns the arguments of every occurrence of `name` that had
+    /// one, in the order they were seen.
+    pub fn opt_strs(&self, name: &str) -> Vec<&str> {
+	match self.opts.get(name) {
+	    Some(occurrences) => occurrences.iter().filter_map(|val| val.as_deref()).collect(),
+	    None => Vec::new()
+	}
+    }
+
+    /// Returns the positional (non-option) command line arguments.
+    pub fn free(&self) -> &[String] {
+	&self.free
+    }
+
+    /// Returns every parsed option and positional argument, in the
+    /// order they appeared on the command line. Unlike `opt_*`/`free`,
+    /// which group occurrences by option name and lose the interleaving
+    /// across different options, this lets a caller that cares about
+    /// relative order (e.g. `od`'s format flags) reconstruct it.
+    pub fn occurrences(&self) -> &[Arg] {
+	&self.occurrences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Assert that a value is a command line option without an option
+    /// argument (i.e. a switch).
+    macro_rules! getopt_assert_opt {
+	($arg:expr, $opt:expr) => {
+	    match (&$arg, &$opt) {
+	    	(Some(Ok(Arg::Opt(opt_val))), val_val) => {
+		    assert_eq!(opt_val, val_val)
+		},
+		_ => panic!("Expected option")
+	    }
+	}
+    }
+
+    /// Asserts that the last argument has been processed.
+    macro_rules! getopt_assert_no_more_args {
+	($arg:expr) => {
+	    match &$arg {
+		Some(a) => panic!("Did not expect argument ({:?})", a),
+		None => ()
+	    }
+	}
+    }
+
+    #[test]
+    fn test_getopt_long_opt() {
+	let args = ["--help"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::with_long("", &[("help", false)], args);
+	match getopt.next() {
+	    Some(Ok(Arg::LongOpt(name))) => assert_eq!("help", name),
+	    _ => panic!("Expected long option")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_long_opt_with_arg_via_space() {
+	let args = ["--offset", "100"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::with_long("", &[("offset", true)], args);
+	match getopt.next() {
+	    Some(Ok(Arg::LongOptWithArg(name, val))) => {
+		assert_eq!("offset", name);
+		assert_eq!("100", val);
+	    },
+	    _ => panic!("Expected long option with argument")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_long_opt_with_arg_via_equals() {
+	let args = ["--offset=100"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::with_long("", &[("offset", true)], args);
+	match getopt.next() {
+	    Some(Ok(Arg::LongOptWithArg(name, val))) => {
+		assert_eq!("offset", name);
+		assert_eq!("100", val);
+	    },
+	    _ => panic!("Expected long option with argument")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_unknown_long_opt() {
+	let args = ["--bogus"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::with_long("", &[("help", false)], args);
+	match getopt.next() {
+	    Some(Err(GetOptErr::UnknownLongOpt(name))) => assert_eq!("bogus", name),
+	    _ => panic!("Expected UnknownLongOpt error")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_usage() {
+	let args = Vec::<String>::new().into_iter();
+	let getopt = GetOpt::with_long("ho:", &[("help", false), ("offset", true)], args)
+	    .describe('h', "print this help message")
+	    .describe('o', "output file")
+	    .describe_long("offset", "starting offset");
+	let usage = getopt.usage("od", "[OPTIONS] [FILE]");
+	assert!(usage.starts_with("usage: od [OPTIONS] [FILE]\n"));
+	assert!(usage.contains("-h"));
+	assert!(usage.contains("print this help message"));
+	assert!(usage.contains("-o <arg>"));
+	assert!(usage.contains("--offset <arg>"));
+	assert!(usage.contains("starting offset"));
+    }
+
+    #[test]
+    fn test_getopt_usage_without_descriptions() {
+	let args = Vec::<String>::new().into_iter();
+	let getopt = GetOpt::new("f", args);
+	let usage = getopt.usage("rm", "[-f] file ...");
+	assert_eq!("usage: rm [-f] file ...\n    -f\n", usage);
+    }
+
+    #[test]
+    fn test_getopt_parse_matches() {
+	let args = ["-v", "-o", "out", "-o", "out2", "ant", "bat"]
+	    .iter().map(|s| s.to_string());
+	let getopt = GetOpt::new("vo:", args);
+	let matches = getopt.parse().unwrap();
+	assert!(matches.opt_present("v"));
+	assert_eq!(1, matches.opt_count("v"));
+	assert_eq!(None, matches.opt_str("v"));
+	assert!(matches.opt_present("o"));
+	assert_eq!(2, matches.opt_count("o"));
+	assert_eq!(Some("out2"), matches.opt_str("o"));
+	assert_eq!(vec!["out", "out2"], matches.opt_strs("o"));
+	assert!(!matches.opt_present("x"));
+	assert_eq!(vec!["ant", "bat"], matches.free());
+    }
+
+    #[test]
+    fn test_getopt_parse_propagates_error() {
+	let args = ["-z"].iter().map(|s| s.to_string());
+	let getopt = GetOpt::new("a", args);
+	match getopt.parse() {
+	    Err(GetOptErr::UnknownOpt(c)) => assert_eq!('z', c),
+	    _ => panic!("Expected UnknownOpt error")
+	}
+    }
+
+    #[test]
+    fn test_getopt_reqopt_builder_missing() {
+	let args = ["-a"].iter().map(|s| s.to_string());
+	let mut getopt = GetOpt::new("ab", args).reqopt('b');
+	getopt_assert_opt!(getopt.next(), 'a');
+	match getopt.next() {
+	    Some(Err(GetOptErr::MissingRequiredOpt(c))) => assert_eq!('b', c),
+	    _ => panic!("Expected MissingRequiredOpt error")
+	}
+	getopt_assert_no_more_args!(getopt.next());
+    }
+
+    #[test]
+    fn test_getopt_reqopt_via_parse() {
+	let args = Vec::<String>::new().into_iter();
+	let getopt = GetOpt::new("f", args).reqopt('f');
+	match getopt.parse() {
+	    Err(GetOptErr::MissingRequiredOpt(c)) => assert_eq!('f', c),
+	    _ => panic!("Expected MissingRequiredOpt error")
+	}
+    }
+}
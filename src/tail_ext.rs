@@ -0,0 +1,262 @@
+// Copyright 2020, 2021 James Bostock. See the LICENSE file at the top-level
+// directory of this distribution.
+
+//! `Input` extensions for reading the tail (or "all but the tail") of a
+//! file or stream, split out of `util/mod.rs` since only `head` uses
+//! them. Included only by `head`, so the rest of the binaries don't
+//! compile in associated items they never call.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use crate::util::Input;
+
+impl Input {
+    /// Returns an iterator over the lines of this input.
+    pub fn lines(self) -> io::Lines<io::BufReader<Input>> {
+        io::BufReader::new(self).lines()
+    }
+
+    /// Returns the last `n` lines of this input. For a seekable
+    /// `Input::File` we walk backwards from the end in fixed-size
+    /// blocks, counting newlines, and then read forward from the
+    /// point we land on. For `Input::Stdin` we cannot seek, so we
+    /// fall back to retaining only the last `n` lines seen as the
+    /// stream is consumed.
+    pub fn read_last_lines(&mut self, n: u64) -> io::Result<Vec<u8>> {
+        match *self {
+            Input::File(ref mut file) => {
+                let offset = tail_line_offset(file, n)?;
+                read_file_from(file, offset)
+            },
+            Input::Stdin(_) => buffer_last_lines(self, n),
+        }
+    }
+
+    /// Returns the last `n` bytes of this input, using the same
+    /// seek-vs-buffer distinction as `read_last_lines`.
+    pub fn read_last_bytes(&mut self, n: u64) -> io::Result<Vec<u8>> {
+        match *self {
+            Input::File(ref mut file) => {
+                let size = file.seek(SeekFrom::End(0))?;
+                let offset = size.saturating_sub(n);
+                read_file_from(file, offset)
+            },
+            Input::Stdin(_) => buffer_last_bytes(self, n),
+        }
+    }
+
+    /// Returns all but the last `n` lines of this input, the
+    /// complement of `read_last_lines` (used by `head`'s negative
+    /// `-n` form).
+    pub fn read_all_but_last_lines(&mut self, n: u64) -> io::Result<Vec<u8>> {
+        match *self {
+            Input::File(ref mut file) => {
+                let offset = tail_line_offset(file, n)?;
+                file.seek(SeekFrom::Start(0))?;
+                read_file_prefix(file, offset)
+            },
+            Input::Stdin(_) => buffer_all_but_last_lines(self, n),
+        }
+    }
+
+    /// Returns all but the last `n` bytes of this input, the
+    /// complement of `read_last_bytes` (used by `head`'s negative
+    /// `-c` form).
+    pub fn read_all_but_last_bytes(&mut self, n: u64) -> io::Result<Vec<u8>> {
+        match *self {
+            Input::File(ref mut file) => {
+                let size = file.seek(SeekFrom::End(0))?;
+                let offset = size.saturating_sub(n);
+                file.seek(SeekFrom::Start(0))?;
+                read_file_prefix(file, offset)
+            },
+            Input::Stdin(_) => buffer_all_but_last_bytes(self, n),
+        }
+    }
+}
+
+/// Seeks `file` to byte `offset` and reads from there to EOF.
+fn read_file_from(file: &mut fs::File, offset: u64) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads the first `len` bytes from `file`, which must already be
+/// positioned at the start.
+fn read_file_prefix(file: &mut fs::File, len: u64) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// The block size used when walking a file backwards from EOF.
+const TAIL_BLOCK_SIZE: u64 = 8192;
+
+/// Finds the byte offset at which the last `n` lines of `file`
+/// begin, by walking backwards from EOF in `TAIL_BLOCK_SIZE` blocks
+/// and counting newlines. A trailing newline at the very end of the
+/// file terminates the last line rather than starting an empty one,
+/// so it is not itself counted.
+fn tail_line_offset(file: &mut fs::File, n: u64) -> io::Result<u64> {
+    let size = file.seek(SeekFrom::End(0))?;
+    if n == 0 {
+        return Ok(size);
+    }
+
+    let mut newlines = 0u64;
+    let mut pos = size;
+    let mut block = vec![0u8; TAIL_BLOCK_SIZE as usize];
+    let mut at_last_byte = true;
+
+    while pos > 0 {
+        let block_len = cmp::min(TAIL_BLOCK_SIZE, pos) as usize;
+        pos -= block_len as u64;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut block[..block_len])?;
+
+        for i in (0..block_len).rev() {
+            let skip = at_last_byte;
+            at_last_byte = false;
+            if block[i] == b'\n' && !skip {
+                newlines += 1;
+                if newlines == n {
+                    return Ok(pos + i as u64 + 1);
+                }
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// Retains only the last `n` lines read from `input` in a bounded
+/// queue, for use when seeking is not available.
+fn buffer_last_lines(input: &mut Input, n: u64) -> io::Result<Vec<u8>> {
+    let mut reader = io::BufReader::new(input);
+    let mut pending: VecDeque<Vec<u8>> = VecDeque::with_capacity(n as usize);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if n == 0 {
+            continue;
+        }
+        if pending.len() as u64 == n {
+            pending.pop_front();
+        }
+        pending.push_back(line.clone());
+    }
+
+    Ok(pending.into_iter().flatten().collect())
+}
+
+/// Retains only the last `n` bytes read from `input` in a bounded
+/// queue, for use when seeking is not available.
+fn buffer_last_bytes(input: &mut Input, n: u64) -> io::Result<Vec<u8>> {
+    let mut pending: VecDeque<u8> = VecDeque::with_capacity(n as usize);
+    let mut buf = [0u8; TAIL_BLOCK_SIZE as usize];
+
+    loop {
+        let read = input.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        for &b in &buf[..read] {
+            if n == 0 {
+                continue;
+            }
+            if pending.len() as u64 == n {
+                pending.pop_front();
+            }
+            pending.push_back(b);
+        }
+    }
+
+    Ok(pending.into_iter().collect())
+}
+
+/// Emits every line read from `input` except the trailing `n`.
+/// Lines are held in a bounded queue of `n` and emitted as they are
+/// pushed out of it by later lines. The complement of
+/// `buffer_last_lines`.
+fn buffer_all_but_last_lines(input: &mut Input, n: u64) -> io::Result<Vec<u8>> {
+    let mut reader = io::BufReader::new(input);
+    let mut pending: VecDeque<Vec<u8>> = VecDeque::with_capacity(n as usize + 1);
+    let mut out = Vec::new();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        pending.push_back(line.clone());
+        if pending.len() as u64 > n {
+            out.extend(pending.pop_front().unwrap());
+        }
+    }
+
+    Ok(out)
+}
+
+/// Emits every byte read from `input` except the trailing `n`. The
+/// complement of `buffer_last_bytes`.
+fn buffer_all_but_last_bytes(input: &mut Input, n: u64) -> io::Result<Vec<u8>> {
+    let mut pending: VecDeque<u8> = VecDeque::with_capacity(n as usize + 1);
+    let mut out = Vec::new();
+    let mut buf = [0u8; TAIL_BLOCK_SIZE as usize];
+
+    loop {
+        let read = input.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        for &b in &buf[..read] {
+            pending.push_back(b);
+            if pending.len() as u64 > n {
+                out.push(pending.pop_front().unwrap());
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Write;
+
+    /// Writes `content` to a fresh temporary file and returns it opened
+    /// as an `Input`.
+    fn input_with_content(name: &str, content: &[u8]) -> Input {
+        let path = std::env::temp_dir()
+            .join(format!("rust-v7-lib-tail-ext-test-{}-{}", std::process::id(), name));
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(content).unwrap();
+        Input::File(fs::File::open(&path).unwrap())
+    }
+
+    #[test]
+    fn test_buffer_last_lines_zero() {
+        let mut input = input_with_content(
+            "buffer_last_lines_zero", b"one\ntwo\nthree\nfour\nfive\n");
+        let got = buffer_last_lines(&mut input, 0).unwrap();
+        assert!(got.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_last_bytes_zero() {
+        let mut input = input_with_content("buffer_last_bytes_zero", b"hello");
+        let got = buffer_last_bytes(&mut input, 0).unwrap();
+        assert!(got.is_empty());
+    }
+}